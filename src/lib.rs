@@ -1,17 +1,358 @@
 use anyhow::Result;
+use base64::Engine as _;
 use spin_sdk::http::{IntoResponse, Request, Response};
 use spin_sdk::http_component;
 use std::io::BufRead;
 use std::io::Cursor;
+use once_cell::sync::OnceCell;
 use std::vec;
+// Pull the shared tract types (`Tensor`, `TractError`, `TypedModel`, `tvec!`,
+// `f32::fact`, …) from whichever backend is enabled so the `tensorflow` and
+// `onnx` features stay independent — an `onnx`-only build must not require
+// `tract_tensorflow` to be compiled.
+#[cfg(feature = "tensorflow")]
 use tract_tensorflow::prelude::*;
+#[cfg(all(feature = "onnx", not(feature = "tensorflow")))]
+use tract_onnx::prelude::*;
+
+/// The runnable plan type shared by both tract backends.
+type Model = TypedRunnableModel<TypedModel>;
+
+/// Channel layout expected by a model's input tensor.
+#[derive(Clone, Copy, Debug)]
+enum Layout {
+    /// `[1, H, W, 3]` — the convention used by the frozen TensorFlow graph.
+    // Each variant is only constructed by one backend, so either is dead when
+    // the other feature is the one enabled.
+    #[allow(dead_code)]
+    Nhwc,
+    /// `[1, 3, H, W]` — the convention used by most ONNX image classifiers.
+    #[allow(dead_code)]
+    Nchw,
+}
+
+/// How a backend wants its input image prepared: target size, channel layout,
+/// per-channel mean/std normalization and the resize filter. This lets the
+/// same component serve models with different input conventions without code
+/// edits — set a build-time default and optionally override per request.
+#[derive(Clone, Copy, Debug)]
+struct Preprocessing {
+    /// Target square edge length in pixels (e.g. 224 or 260).
+    size: u32,
+    /// Channel layout of the input tensor.
+    layout: Layout,
+    /// Per-channel mean subtracted after scaling to `[0, 1]`.
+    mean: [f32; 3],
+    /// Per-channel standard deviation the centered value is divided by.
+    std: [f32; 3],
+    /// Resize filter used to scale the image to `size`.
+    filter: image::imageops::FilterType,
+}
+
+impl Preprocessing {
+    /// The build-time default: `pixel / 255` in NHWC layout with no
+    /// mean/std normalization, matching the frozen MobileNet graph.
+    #[allow(dead_code)] // only referenced by the TensorFlow backend
+    const DEFAULT: Self = Self {
+        size: 224,
+        layout: Layout::Nhwc,
+        mean: [0.0, 0.0, 0.0],
+        std: [1.0, 1.0, 1.0],
+        filter: image::imageops::FilterType::Triangle,
+    };
+}
+
+/// A model backend that turns a prepared input tensor into raw class logits.
+trait Classifier: Send + Sync {
+    /// Describe how input images should be resized, laid out and normalized.
+    fn preprocessing(&self) -> Preprocessing;
+
+    /// Run inference on a prepared input tensor and return the raw logits.
+    fn run(&self, image: Tensor) -> Result<Vec<f32>, ClassificationError>;
+
+    /// Run inference on a batched `[N, ...]` input tensor in a single call and
+    /// return the per-image logits in input order.
+    fn run_batch(&self, images: Tensor, batch: usize)
+        -> Result<Vec<Vec<f32>>, ClassificationError>;
+}
+
+/// Split a flat `[N * classes]` logit buffer into `batch` equal rows.
+fn split_batch(flat: Vec<f32>, batch: usize) -> Result<Vec<Vec<f32>>, ClassificationError> {
+    if batch == 0 || !flat.len().is_multiple_of(batch) {
+        return Err(ClassificationError::Unclassified);
+    }
+    let classes = flat.len() / batch;
+    Ok(flat.chunks(classes).map(|row| row.to_vec()).collect())
+}
+
+/// Backend wrapping a frozen TensorFlow graph via `tract_tensorflow`.
+#[cfg(feature = "tensorflow")]
+struct TensorflowClassifier {
+    model: Model,
+}
+
+#[cfg(feature = "tensorflow")]
+impl TensorflowClassifier {
+    fn load() -> Result<Self, ClassificationError> {
+        // Use a symbolic batch dimension so the cached plan serves both single
+        // and batched requests without rebuilding per request.
+        let model = tract_tensorflow::tensorflow().model_for_read(&mut Cursor::new(
+            include_bytes!("../mobilenet_v2_1.4_224_frozen.pb"),
+        ))?;
+        let n = model.symbols.sym("N").to_dim();
+        let model = model
+            .with_input_fact(
+                0,
+                f32::fact([n, 224.to_dim(), 224.to_dim(), 3.to_dim()]).into(),
+            )?
+            .into_optimized()?
+            .into_runnable()?;
+
+        println!("[Rust classifier]: Loaded Tensorflow model.");
+        Ok(Self { model })
+    }
+}
+
+#[cfg(feature = "tensorflow")]
+impl Classifier for TensorflowClassifier {
+    fn preprocessing(&self) -> Preprocessing {
+        Preprocessing::DEFAULT
+    }
+
+    fn run(&self, image: Tensor) -> Result<Vec<f32>, ClassificationError> {
+        let result = self.model.run(tvec!(image.into()))?;
+        Ok(result[0].to_array_view::<f32>()?.iter().cloned().collect())
+    }
+
+    fn run_batch(
+        &self,
+        images: Tensor,
+        batch: usize,
+    ) -> Result<Vec<Vec<f32>>, ClassificationError> {
+        let result = self.model.run(tvec!(images.into()))?;
+        let flat: Vec<f32> = result[0].to_array_view::<f32>()?.iter().cloned().collect();
+        split_batch(flat, batch)
+    }
+}
+
+/// Backend wrapping an ONNX model (e.g. EfficientNet-B2) via `tract_onnx`.
+// Dead when both features are on, since `build_classifier` prefers TensorFlow.
+#[cfg(feature = "onnx")]
+#[allow(dead_code)]
+struct OnnxClassifier {
+    model: Model,
+}
+
+#[cfg(feature = "onnx")]
+#[allow(dead_code)] // see the note on the struct
+impl OnnxClassifier {
+    /// EfficientNet-B2 expects 260×260 NCHW inputs.
+    const SIZE: u32 = 260;
+
+    fn load() -> Result<Self, ClassificationError> {
+        // Symbolic batch dimension (see the TensorFlow backend for rationale).
+        let model = tract_onnx::onnx()
+            .model_for_read(&mut Cursor::new(include_bytes!("../model.onnx")))?;
+        let n = model.symbols.sym("N").to_dim();
+        let size = (Self::SIZE as i64).to_dim();
+        let model = model
+            .with_input_fact(0, f32::fact([n, 3.to_dim(), size.clone(), size]).into())?
+            .into_optimized()?
+            .into_runnable()?;
+
+        println!("[Rust classifier]: Loaded ONNX model.");
+        Ok(Self { model })
+    }
+}
+
+#[cfg(feature = "onnx")]
+impl Classifier for OnnxClassifier {
+    fn preprocessing(&self) -> Preprocessing {
+        // EfficientNet-B2 is an ImageNet model: NCHW with standard
+        // per-channel mean/std normalization.
+        Preprocessing {
+            size: Self::SIZE,
+            layout: Layout::Nchw,
+            mean: [0.485, 0.456, 0.406],
+            std: [0.229, 0.224, 0.225],
+            filter: image::imageops::FilterType::Triangle,
+        }
+    }
+
+    fn run(&self, image: Tensor) -> Result<Vec<f32>, ClassificationError> {
+        let result = self.model.run(tvec!(image.into()))?;
+        Ok(result[0].to_array_view::<f32>()?.iter().cloned().collect())
+    }
+
+    fn run_batch(
+        &self,
+        images: Tensor,
+        batch: usize,
+    ) -> Result<Vec<Vec<f32>>, ClassificationError> {
+        let result = self.model.run(tvec!(images.into()))?;
+        let flat: Vec<f32> = result[0].to_array_view::<f32>()?.iter().cloned().collect();
+        split_batch(flat, batch)
+    }
+}
+
+/// Process-wide classifier, built lazily on the first request and reused
+/// thereafter so we don't pay the read/optimize/runnable cost per request.
+static CLASSIFIER: OnceCell<Box<dyn Classifier>> = OnceCell::new();
+
+/// Build the classifier backend selected at compile time via Cargo features.
+#[allow(clippy::needless_return)] // the `return`s keep the cfg arms uniform
+fn build_classifier() -> Result<Box<dyn Classifier>, ClassificationError> {
+    #[cfg(feature = "tensorflow")]
+    {
+        return Ok(Box::new(TensorflowClassifier::load()?));
+    }
+    #[cfg(all(feature = "onnx", not(feature = "tensorflow")))]
+    {
+        return Ok(Box::new(OnnxClassifier::load()?));
+    }
+    #[cfg(not(any(feature = "tensorflow", feature = "onnx")))]
+    compile_error!("enable the `tensorflow` or `onnx` feature");
+}
+
+/// Return the shared classifier, building and warming it on first use.
+///
+/// Build and warmup happen once, inside the first request that calls this;
+/// warmup runs a single dummy inference before that request's own input, and
+/// later requests reuse the warmed plan. The backend is `Send + Sync`, so
+/// concurrent Spin invocations can share it safely.
+fn classifier() -> Result<&'static dyn Classifier, ClassificationError> {
+    // A single fallible initialization so the expensive build/warm happens
+    // exactly once even when concurrent first requests race here.
+    CLASSIFIER
+        .get_or_try_init(|| {
+            let classifier = build_classifier()?;
+
+            // Warm the plan with a zeroed input before any real input runs.
+            classifier.run(zeroed_input(classifier.preprocessing()))?;
+            println!("[Rust classifier]: Warmed up model.");
+
+            Ok(classifier)
+        })
+        .map(|classifier| classifier.as_ref())
+}
+
+/// Build a zeroed input tensor matching the backend's preprocessing layout.
+fn zeroed_input(prep: Preprocessing) -> Tensor {
+    let size = prep.size as usize;
+    match prep.layout {
+        Layout::Nhwc => tract_ndarray::Array4::<f32>::zeros((1, size, size, 3)).into(),
+        Layout::Nchw => tract_ndarray::Array4::<f32>::zeros((1, 3, size, size)).into(),
+    }
+}
+
+/// Decode, resize and normalize an image into the tensor layout the backend
+/// expects. Each channel is scaled to `[0, 1]`, then centered and scaled by
+/// the per-channel mean/std from `prep`; the layout follows `prep.layout`.
+fn preprocess(img: &[u8], prep: Preprocessing) -> Result<Tensor, ClassificationError> {
+    let size = prep.size;
+    let image = image::load_from_memory(img)?.to_rgb8();
+    let resized = image::imageops::resize(&image, size, size, prep.filter);
+    println!("[Rust classifier]: Resized image to {0}x{0} px.", size);
+
+    let normalize =
+        |value: u8, channel: usize| (value as f32 / 255.0 - prep.mean[channel]) / prep.std[channel];
+
+    let size = size as usize;
+    let tensor: Tensor = match prep.layout {
+        Layout::Nhwc => {
+            tract_ndarray::Array4::from_shape_fn((1, size, size, 3), |(_, y, x, c)| {
+                normalize(resized[(x as _, y as _)][c], c)
+            })
+            .into()
+        }
+        Layout::Nchw => {
+            tract_ndarray::Array4::from_shape_fn((1, 3, size, size), |(_, c, y, x)| {
+                normalize(resized[(x as _, y as _)][c], c)
+            })
+            .into()
+        }
+    };
+
+    Ok(tensor)
+}
+
+/// Decode and normalize `N` images into a single batched `[N, ...]` tensor so
+/// they can be classified in one model invocation. Images keep their input
+/// order along the batch axis.
+fn preprocess_batch(images: &[Vec<u8>], prep: Preprocessing) -> Result<Tensor, ClassificationError> {
+    let n = images.len();
+    let size = prep.size;
+
+    // Decode and resize every image up front so the shape function can index
+    // into them by batch position.
+    let resized: Vec<_> = images
+        .iter()
+        .map(|img| {
+            let image = image::load_from_memory(img)?.to_rgb8();
+            Ok(image::imageops::resize(&image, size, size, prep.filter))
+        })
+        .collect::<Result<Vec<_>, ClassificationError>>()?;
+    println!("[Rust classifier]: Resized {n} images to {size}x{size} px.");
+
+    let normalize = |value: u8, channel: usize| {
+        (value as f32 / 255.0 - prep.mean[channel]) / prep.std[channel]
+    };
+
+    let size = size as usize;
+    let tensor: Tensor = match prep.layout {
+        Layout::Nhwc => tract_ndarray::Array4::from_shape_fn((n, size, size, 3), |(b, y, x, c)| {
+            normalize(resized[b][(x as _, y as _)][c], c)
+        })
+        .into(),
+        Layout::Nchw => tract_ndarray::Array4::from_shape_fn((n, 3, size, size), |(b, c, y, x)| {
+            normalize(resized[b][(x as _, y as _)][c], c)
+        })
+        .into(),
+    };
+
+    Ok(tensor)
+}
+
+/// Apply optional per-request preprocessing overrides from request headers.
+///
+/// Supported headers (all optional): `x-preproc-mean` / `x-preproc-std` as
+/// three comma-separated floats. The target `size` is deliberately *not*
+/// overridable: each backend pins the model's input fact to a fixed spatial
+/// size (224 for TF, 260 for ONNX), so any other size would only produce a
+/// shape-mismatch `ModelError` at `run` time.
+fn apply_header_overrides(mut prep: Preprocessing, req: &Request) -> Preprocessing {
+    if let Some(mean) = req
+        .header("x-preproc-mean")
+        .and_then(|v| v.as_str())
+        .and_then(parse_triple)
+    {
+        prep.mean = mean;
+    }
+    if let Some(std) = req
+        .header("x-preproc-std")
+        .and_then(|v| v.as_str())
+        .and_then(parse_triple)
+    {
+        prep.std = std;
+    }
+    prep
+}
+
+/// Parse three comma-separated floats, e.g. `0.485,0.456,0.406`.
+fn parse_triple(value: &str) -> Option<[f32; 3]> {
+    let parts: Vec<f32> = value.split(',').filter_map(|p| p.trim().parse().ok()).collect();
+    match parts.as_slice() {
+        [a, b, c] => Some([*a, *b, *c]),
+        _ => None,
+    }
+}
 
 /// A simple Spin HTTP component.
 #[http_component]
 fn handle_spin_image_classification_demo(req: Request) -> anyhow::Result<impl IntoResponse> {
-    let image = req.body().to_vec();
+    let body = req.body().to_vec();
 
-    if image.is_empty() {
+    if body.is_empty() {
         return Ok(Response::builder()
             .status(400)
             .header("content-type", "text/plain")
@@ -19,95 +360,374 @@ fn handle_spin_image_classification_demo(req: Request) -> anyhow::Result<impl In
             .build());
     }
 
-    println!(
-        "[Rust classifier]: Received image with {} bytes.",
-        image.len()
-    );
+    let top_k = top_k_from_query(req.query());
+
+    // A `?mode=eval` request carries a dataset manifest and reports accuracy
+    // rather than classifying a single payload.
+    if query_has_flag(req.query(), "mode", "eval") {
+        let manifest = String::from_utf8_lossy(&body);
+        return match evaluate(&req, &manifest) {
+            Ok(report) => Ok(Response::builder()
+                .status(200)
+                .header("content-type", "application/json")
+                .body(report)
+                .build()),
+            Err(err) => Ok(error_response(&err)),
+        };
+    }
 
-    let classification_result = classify(image);
+    // A JSON body carries several base64-encoded images to classify as one
+    // batch; a raw body is a single image.
+    let is_batch = req
+        .header("content-type")
+        .and_then(|v| v.as_str())
+        .map(|ct| ct.contains("application/json"))
+        .unwrap_or(false);
 
-    if classification_result.is_err() {
-        eprintln!(
-            "[Rust classifier]: Error during classification: {:?}",
-            classification_result.err()
-        );
+    if is_batch {
+        let images = match decode_batch(&body) {
+            Ok(images) => images,
+            Err(err) => return Ok(error_response(&err)),
+        };
+        println!("[Rust classifier]: Received batch of {} images.", images.len());
+
+        let results = match classify_batch(&req, &images, top_k) {
+            Ok(results) => results,
+            Err(err) => return Ok(error_response(&err)),
+        };
 
+        // One JSON array of predictions per image, in input order.
+        let per_image: Vec<String> = results.iter().map(|p| predictions_json(p)).collect();
+        let body = format!("[{}]", per_image.join(","));
         return Ok(Response::builder()
-            .status(500)
-            .header("content-type", "text/plain")
-            .body(format!("Error during classification",))
+            .status(200)
+            .header("content-type", "application/json")
+            .body(body)
             .build());
     }
 
-    // If we have a successful classification, return the result.
-    let (label, probability) = classification_result.unwrap();
-    let body = format!(
-        "{{\"Predicted label\": \"{}\", \"Probability\": {:.4}}}",
-        label, probability
+    println!(
+        "[Rust classifier]: Received image with {} bytes.",
+        body.len()
     );
+
+    let predictions = match classify(&req, body, top_k) {
+        Ok(predictions) => predictions,
+        Err(err) => return Ok(error_response(&err)),
+    };
+
+    // Return the top-K predictions as a JSON array ordered by descending
+    // probability.
+    let body = predictions_json(&predictions);
     Ok(Response::builder()
         .status(200)
-        .header("content-type", "text/plain")
+        .header("content-type", "application/json")
         .body(body)
         .build())
 }
 
+/// Serialize top-K predictions as a JSON array of `{label, probability}`.
+fn predictions_json(predictions: &[ClassificationResult]) -> String {
+    let items: Vec<String> = predictions
+        .iter()
+        .map(|(label, probability)| {
+            format!(
+                "{{\"label\":\"{}\",\"probability\":{:.4}}}",
+                json_escape(label),
+                probability
+            )
+        })
+        .collect();
+    format!("[{}]", items.join(","))
+}
+
+/// Decode a JSON array of base64-encoded image blobs into raw image bytes.
+fn decode_batch(body: &[u8]) -> Result<Vec<Vec<u8>>, ClassificationError> {
+    let blobs: Vec<String> = serde_json::from_slice(body)
+        .map_err(|e| ClassificationError::ImageError(e.to_string()))?;
+    blobs
+        .iter()
+        .map(|blob| {
+            base64::engine::general_purpose::STANDARD
+                .decode(blob)
+                .map_err(|e| ClassificationError::ImageError(e.to_string()))
+        })
+        .collect()
+}
+
 type ClassificationResult = (String, f32);
 
+/// Return true when the query string contains `key=value`.
+fn query_has_flag(query: &str, key: &str, value: &str) -> bool {
+    let target = format!("{key}={value}");
+    query.split('&').any(|pair| pair == target)
+}
+
+/// Parse the `top_k` query parameter, defaulting to 1 when absent or invalid.
+fn top_k_from_query(query: &str) -> usize {
+    query
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("top_k="))
+        .and_then(|value| value.parse::<usize>().ok())
+        .filter(|k| *k >= 1)
+        .unwrap_or(1)
+}
+
+/// Convert raw logits into a probability distribution using a numerically
+/// stable softmax (`exp(x_i - max_x) / sum(exp(x_j - max_x))`). MobileNet's
+/// frozen graph output is not guaranteed to be normalized.
+fn softmax(logits: &[f32]) -> Vec<f32> {
+    let max = logits.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
+    let exps: Vec<f32> = logits.iter().map(|x| (x - max).exp()).collect();
+    let sum: f32 = exps.iter().sum();
+    exps.into_iter().map(|e| e / sum).collect()
+}
+
 #[derive(Debug)]
 enum ClassificationError {
     ModelError(String),
     ImageError(String),
     IoError(String),
-    Unknown(String),
+    LabelError(String),
     Unclassified,
 }
 
-fn classify(img: Vec<u8>) -> Result<ClassificationResult, ClassificationError> {
-    let model = tract_tensorflow::tensorflow()
-        .model_for_read(&mut Cursor::new(include_bytes!(
-            "../mobilenet_v2_1.4_224_frozen.pb"
-        )))?
-        .with_input_fact(0, f32::fact([1, 224, 224, 3]).into())?
-        .into_optimized()?
-        .into_runnable()?;
+impl ClassificationError {
+    /// A stable machine-readable tag for the error, surfaced as `"kind"`.
+    fn kind(&self) -> &'static str {
+        match self {
+            ClassificationError::ModelError(_) => "ModelError",
+            ClassificationError::ImageError(_) => "ImageError",
+            ClassificationError::IoError(_) => "IoError",
+            ClassificationError::LabelError(_) => "LabelError",
+            ClassificationError::Unclassified => "Unclassified",
+        }
+    }
+
+    /// HTTP status for the error: client data problems are 4xx, everything
+    /// else is a 5xx server/model error.
+    fn status(&self) -> u16 {
+        match self {
+            ClassificationError::ImageError(_) => 400,
+            _ => 500,
+        }
+    }
+}
+
+impl std::fmt::Display for ClassificationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ClassificationError::ModelError(msg) => write!(f, "model error: {msg}"),
+            ClassificationError::ImageError(msg) => write!(f, "image error: {msg}"),
+            ClassificationError::IoError(msg) => write!(f, "io error: {msg}"),
+            ClassificationError::LabelError(msg) => write!(f, "label error: {msg}"),
+            ClassificationError::Unclassified => write!(f, "image could not be classified"),
+        }
+    }
+}
 
-    println!("[Rust classifier]: Loaded Tensorflow model.");
+impl std::error::Error for ClassificationError {}
 
-    let image = image::load_from_memory(&img)?.to_rgb8();
-    let resized =
-        image::imageops::resize(&image, 224, 224, ::image::imageops::FilterType::Triangle);
-    let image: Tensor = tract_ndarray::Array4::from_shape_fn((1, 224, 224, 3), |(_, y, x, c)| {
-        resized[(x as _, y as _)][c] as f32 / 255.0
-    })
-    .into();
+/// Build a structured JSON error response, e.g.
+/// `{"error":"...","kind":"ModelError"}`, with the error's HTTP status.
+fn error_response(err: &ClassificationError) -> Response {
+    eprintln!("[Rust classifier]: {err}");
+    let body = format!(
+        "{{\"error\":\"{}\",\"kind\":\"{}\"}}",
+        json_escape(&err.to_string()),
+        err.kind()
+    );
+    Response::builder()
+        .status(err.status())
+        .header("content-type", "application/json")
+        .body(body)
+        .build()
+}
+
+/// Escape a string for safe inclusion inside a JSON string literal.
+fn json_escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
 
-    println!("[Rust classifier]: Resized image to 224x224 px.");
+fn classify(
+    req: &Request,
+    img: Vec<u8>,
+    top_k: usize,
+) -> Result<Vec<ClassificationResult>, ClassificationError> {
+    let classifier = classifier()?;
+    let prep = apply_header_overrides(classifier.preprocessing(), req);
+
+    let input = preprocess(&img, prep)?;
 
     // run the model on the input
-    let result = model.run(tvec!(image.into()))?;
-    // find and display the max value with its index
-    let best = result[0]
-        .to_array_view::<f32>()?
+    let logits = classifier.run(input)?;
+    top_k_predictions(&logits, top_k)
+}
+
+/// Classify several images carried in one request as a single batched tensor,
+/// returning the per-image top-K predictions in input order.
+fn classify_batch(
+    req: &Request,
+    images: &[Vec<u8>],
+    top_k: usize,
+) -> Result<Vec<Vec<ClassificationResult>>, ClassificationError> {
+    let classifier = classifier()?;
+    let prep = apply_header_overrides(classifier.preprocessing(), req);
+
+    let input = preprocess_batch(images, prep)?;
+
+    // Run the whole batch through the model in a single invocation.
+    let logits = classifier.run_batch(input, images.len())?;
+    logits
         .iter()
-        .cloned()
-        .zip(1..)
-        .max_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
-
-    match best {
-        Some((probability, class)) => {
-            let label = get_label(class)?;
-            println!(
-                "[Rust classifier]: Probability: {}, class: {}.",
-                probability, label
-            );
-            return Ok((label, probability));
+        .map(|row| top_k_predictions(row, top_k))
+        .collect()
+}
+
+/// A parsed dataset entry: an image path paired with its ground-truth class
+/// label. Following the ArmNN `ParseDataset` format, `label_id` is a 0-based
+/// index into the model's output vector — equivalently, a 0-based line number
+/// in the shipped `labels.txt`. For the MobileNet-v2 frozen graph that vector
+/// has 1001 entries with the background class at index 0, so a conventional
+/// 0–999 ImageNet ground truth (no background) must be shifted by one before
+/// it is used here.
+struct DatasetEntry {
+    image_path: String,
+    label_id: usize,
+}
+
+/// Parse a dataset manifest of `image_path label_id` lines, matching the
+/// format consumed by the ArmNN TfLite test harness's `ParseDataset`. Blank
+/// and malformed lines are skipped.
+fn parse_dataset(manifest: &str) -> Vec<DatasetEntry> {
+    manifest
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let image_path = parts.next()?;
+            let label_id = parts.next()?.parse::<usize>().ok()?;
+            Some(DatasetEntry {
+                image_path: image_path.to_string(),
+                label_id,
+            })
+        })
+        .collect()
+}
+
+/// Run every image in a dataset manifest through the classifier and report
+/// aggregate top-1 / top-5 accuracy plus a per-image predicted-vs-expected
+/// breakdown. Reuses the top-K machinery so top-5 membership is checked
+/// against the expected class.
+///
+/// Each `image_path` is read from the component's filesystem. Because the
+/// component runs in the Wasmtime sandbox it can only see paths exposed by a
+/// `files` mount in the Spin manifest, e.g.
+///
+/// ```toml
+/// [[component]]
+/// # ...
+/// files = [{ source = "testdata", destination = "/testdata" }]
+/// ```
+///
+/// so manifest paths must be relative to a mounted destination (e.g.
+/// `/testdata/cat.jpg`); unmounted host paths surface as an `IoError`.
+fn evaluate(req: &Request, manifest: &str) -> Result<String, ClassificationError> {
+    let classifier = classifier()?;
+    let prep = apply_header_overrides(classifier.preprocessing(), req);
+    let entries = parse_dataset(manifest);
+
+    let mut top1 = 0usize;
+    let mut top5 = 0usize;
+    let mut breakdown = Vec::with_capacity(entries.len());
+
+    for entry in &entries {
+        let img = std::fs::read(&entry.image_path)
+            .map_err(|e| ClassificationError::IoError(e.to_string()))?;
+        let input = preprocess(&img, prep)?;
+        let logits = classifier.run(input)?;
+        let scored = top_k_scored(&logits, 5)?;
+
+        // `top_k_scored` yields 1-based classes (matching `get_label`'s
+        // `nth(num - 1)`), whereas `label_id` is a 0-based index into the same
+        // output/`labels.txt` space, so the only adjustment is +1 to reach the
+        // 1-based class. See `DatasetEntry` for the required label convention.
+        let expected_class = entry.label_id + 1;
+        let predicted = scored
+            .first()
+            .map(|(class, _)| *class)
+            .ok_or(ClassificationError::Unclassified)?;
+        let in_top5 = scored.iter().any(|(class, _)| *class == expected_class);
+
+        if predicted == expected_class {
+            top1 += 1;
+        }
+        if in_top5 {
+            top5 += 1;
         }
-        None => return Err(ClassificationError::Unclassified),
+
+        // Report both fields as 0-based class indices for parity with the
+        // input manifest.
+        breakdown.push(format!(
+            "{{\"image\":\"{}\",\"expected\":{},\"predicted\":{},\"top5\":{}}}",
+            json_escape(&entry.image_path),
+            entry.label_id,
+            predicted - 1,
+            in_top5
+        ));
     }
+
+    let count = entries.len();
+    let divisor = count.max(1) as f32;
+    Ok(format!(
+        "{{\"count\":{},\"top1_accuracy\":{:.4},\"top5_accuracy\":{:.4},\"per_image\":[{}]}}",
+        count,
+        top1 as f32 / divisor,
+        top5 as f32 / divisor,
+        breakdown.join(",")
+    ))
+}
+
+/// Normalize raw logits into probabilities and return the top-K scoring
+/// `(class, probability)` pairs (1-indexed class), sorted descending.
+fn top_k_scored(logits: &[f32], top_k: usize) -> Result<Vec<(usize, f32)>, ClassificationError> {
+    if logits.is_empty() {
+        return Err(ClassificationError::Unclassified);
+    }
+    let probabilities = softmax(logits);
+
+    // Pair each probability with its (1-indexed) class and sort descending.
+    let mut scored: Vec<(usize, f32)> = probabilities
+        .into_iter()
+        .zip(1..)
+        .map(|(probability, class)| (class, probability))
+        .collect();
+    scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+    scored.truncate(top_k);
+    Ok(scored)
+}
+
+/// Normalize raw logits into probabilities and return the top-K scoring
+/// classes as `(label, probability)`, sorted by descending probability.
+fn top_k_predictions(
+    logits: &[f32],
+    top_k: usize,
+) -> Result<Vec<ClassificationResult>, ClassificationError> {
+    let scored = top_k_scored(logits, top_k)?;
+
+    let mut predictions = Vec::with_capacity(scored.len());
+    for (class, probability) in scored {
+        let label = get_label(class)?;
+        println!(
+            "[Rust classifier]: Probability: {}, class: {}.",
+            probability, label
+        );
+        predictions.push((label, probability));
+    }
+
+    Ok(predictions)
 }
 
-fn get_label(num: usize) -> Result<String, anyhow::Error> {
+fn get_label(num: usize) -> Result<String, ClassificationError> {
     // The result of executing the inference is the predicted class,
     // which also indicates the line number in the (1-indexed) labels file.
     let labels = include_bytes!("../labels.txt");
@@ -115,8 +735,8 @@ fn get_label(num: usize) -> Result<String, anyhow::Error> {
     content
         .lines()
         .nth(num - 1)
-        .expect("cannot get prediction label")
-        .map_err(|err| anyhow::Error::new(err))
+        .ok_or_else(|| ClassificationError::LabelError(format!("no label for class {num}")))?
+        .map_err(|err| ClassificationError::IoError(err.to_string()))
 }
 
 impl From<TractError> for ClassificationError {
@@ -130,3 +750,88 @@ impl From<image::ImageError> for ClassificationError {
         ClassificationError::ImageError(e.to_string())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn softmax_normalizes_to_a_distribution() {
+        let probs = softmax(&[1.0, 2.0, 3.0]);
+        let sum: f32 = probs.iter().sum();
+        assert!((sum - 1.0).abs() < 1e-6);
+        // Order is preserved: the largest logit keeps the largest probability.
+        assert!(probs[2] > probs[1] && probs[1] > probs[0]);
+    }
+
+    #[test]
+    fn softmax_is_numerically_stable_for_large_logits() {
+        // Without the `x - max` shift these would overflow to `inf`/`NaN`.
+        let probs = softmax(&[1000.0, 1001.0, 1002.0]);
+        let sum: f32 = probs.iter().sum();
+        assert!((sum - 1.0).abs() < 1e-6);
+        assert!(probs.iter().all(|p| p.is_finite()));
+    }
+
+    #[test]
+    fn split_batch_divides_into_equal_rows() {
+        let rows = split_batch(vec![1.0, 2.0, 3.0, 4.0], 2).unwrap();
+        assert_eq!(rows, vec![vec![1.0, 2.0], vec![3.0, 4.0]]);
+    }
+
+    #[test]
+    fn split_batch_rejects_zero_and_indivisible_batches() {
+        assert!(split_batch(vec![1.0, 2.0], 0).is_err());
+        assert!(split_batch(vec![1.0, 2.0, 3.0], 2).is_err());
+    }
+
+    #[test]
+    fn parse_dataset_skips_blank_and_malformed_lines() {
+        let manifest = "\
+images/cat.jpg 281
+
+images/dog.jpg 207
+missing_label_only
+images/bird.jpg not_a_number
+images/fish.jpg 0
+";
+        let entries = parse_dataset(manifest);
+        let parsed: Vec<(&str, usize)> = entries
+            .iter()
+            .map(|e| (e.image_path.as_str(), e.label_id))
+            .collect();
+        assert_eq!(
+            parsed,
+            vec![
+                ("images/cat.jpg", 281),
+                ("images/dog.jpg", 207),
+                ("images/fish.jpg", 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn top_k_from_query_defaults_and_parses() {
+        assert_eq!(top_k_from_query(""), 1);
+        assert_eq!(top_k_from_query("top_k=5"), 5);
+        assert_eq!(top_k_from_query("foo=bar&top_k=3"), 3);
+        // Invalid or below-range values fall back to the default.
+        assert_eq!(top_k_from_query("top_k=0"), 1);
+        assert_eq!(top_k_from_query("top_k=abc"), 1);
+    }
+
+    #[test]
+    fn parse_triple_requires_exactly_three_floats() {
+        assert_eq!(parse_triple("0.485,0.456,0.406"), Some([0.485, 0.456, 0.406]));
+        assert_eq!(parse_triple("1, 2 , 3"), Some([1.0, 2.0, 3.0]));
+        assert_eq!(parse_triple("1.0,2.0"), None);
+        assert_eq!(parse_triple("1.0,2.0,3.0,4.0"), None);
+    }
+
+    #[test]
+    fn json_escape_escapes_quotes_and_backslashes() {
+        assert_eq!(json_escape(r"images\cat.jpg"), r"images\\cat.jpg");
+        assert_eq!(json_escape(r#"a"b"#), r#"a\"b"#);
+        assert_eq!(json_escape("plain"), "plain");
+    }
+}